@@ -0,0 +1,188 @@
+//! Bearer-token authentication for the DNS verification service.
+//!
+//! This module issues and validates HS256 JSON Web Tokens. A token carries the
+//! authenticated user id (`sub`) and a role claim (`admin` or `user`), mirroring
+//! the token + role model used by the Nomilo API. Handlers obtain the verified
+//! identity through the [`AuthenticatedUser`] extractor rather than trusting a
+//! `user_id` supplied in the request body.
+
+use std::env;
+use std::future::{ready, Ready};
+
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, Responder};
+use actix_web::dev::Payload;
+use actix_web::error::{ErrorInternalServerError, ErrorUnauthorized};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+
+/// How long, in seconds, an issued token stays valid.
+const TOKEN_TTL_SECONDS: u64 = 60 * 60 * 24;
+
+/// The role a principal holds within the service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// May inspect and manage any user's records.
+    Admin,
+    /// Scoped to their own records.
+    User,
+}
+
+/// The claims embedded in a signed token.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Claims {
+    /// The authenticated user id.
+    pub sub: String,
+    /// The role granted to the user.
+    pub role: Role,
+    /// Expiry as a Unix timestamp (seconds).
+    pub exp: usize,
+}
+
+/// The verified identity injected into handlers.
+#[derive(Debug, Clone)]
+pub struct AuthenticatedUser {
+    /// The authenticated user id, taken from the token's `sub` claim.
+    pub user_id: String,
+    /// The role granted to the user.
+    pub role: Role,
+}
+
+impl AuthenticatedUser {
+    /// Returns `true` if this user may act on behalf of `user_id`.
+    ///
+    /// Admins may act for anyone; a plain user only for themselves.
+    pub fn can_access(&self, user_id: &str) -> bool {
+        self.role == Role::Admin || self.user_id == user_id
+    }
+}
+
+/// Reads the signing secret from the `JWT_SECRET` environment variable.
+fn jwt_secret() -> Result<String, String> {
+    env::var("JWT_SECRET").map_err(|_| "JWT_SECRET must be set".to_string())
+}
+
+/// Issues a signed HS256 token for the given user and role.
+pub fn issue_token(user_id: &str, role: Role) -> Result<String, String> {
+    let secret = jwt_secret()?;
+    let exp = current_unix_time()
+        .saturating_add(TOKEN_TTL_SECONDS) as usize;
+    let claims = Claims {
+        sub: user_id.to_string(),
+        role,
+        exp,
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|e| format!("Failed to issue token: {}", e))
+}
+
+/// Returns the current time as a Unix timestamp in seconds.
+fn current_unix_time() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(extract_user(req))
+    }
+}
+
+/// Validates the `Authorization: Bearer` header and returns the identity.
+fn extract_user(req: &HttpRequest) -> Result<AuthenticatedUser, actix_web::Error> {
+    let header = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| ErrorUnauthorized("Missing Authorization header"))?;
+
+    let token = header
+        .strip_prefix("Bearer ")
+        .ok_or_else(|| ErrorUnauthorized("Expected a Bearer token"))?;
+
+    let secret = jwt_secret().map_err(ErrorInternalServerError)?;
+
+    let data = decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|_| ErrorUnauthorized("Invalid or expired token"))?;
+
+    Ok(AuthenticatedUser {
+        user_id: data.claims.sub,
+        role: data.claims.role,
+    })
+}
+
+/// Represents a request to the login endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginRequest {
+    /// The user id to authenticate.
+    pub user_id: String,
+    /// The shared password guarding token issuance.
+    pub password: String,
+}
+
+/// Represents the response of a successful login.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginResponse {
+    /// The signed bearer token.
+    pub token: String,
+    /// The role granted to the user.
+    pub role: Role,
+}
+
+/// Determines the role for `user_id` from the `ADMIN_USERS` env var.
+///
+/// `ADMIN_USERS` is a comma-separated list of user ids that receive the
+/// `admin` role; everyone else receives `user`.
+fn role_for(user_id: &str) -> Role {
+    let admins = env::var("ADMIN_USERS").unwrap_or_default();
+    if admins.split(',').any(|id| id.trim() == user_id) {
+        Role::Admin
+    } else {
+        Role::User
+    }
+}
+
+/// Issues a bearer token after checking the shared password.
+///
+/// The password is compared against the `AUTH_PASSWORD` environment variable;
+/// the granted role is derived from `ADMIN_USERS`. This keeps the endpoint in
+/// line with the token + role model rather than introducing a user store.
+///
+/// # Arguments
+///
+/// * `login_request` - A JSON payload containing the user id and password.
+///
+/// # Returns
+///
+/// An implementation of `Responder` carrying the signed token on success.
+pub async fn login(login_request: web::Json<LoginRequest>) -> impl Responder {
+    let expected = match env::var("AUTH_PASSWORD") {
+        Ok(value) => value,
+        Err(_) => {
+            return HttpResponse::InternalServerError().body("AUTH_PASSWORD must be set")
+        }
+    };
+
+    if login_request.password != expected {
+        return HttpResponse::Unauthorized().json("Invalid credentials");
+    }
+
+    let role = role_for(&login_request.user_id);
+    match issue_token(&login_request.user_id, role) {
+        Ok(token) => HttpResponse::Ok().json(LoginResponse { token, role }),
+        Err(e) => HttpResponse::InternalServerError().body(e),
+    }
+}
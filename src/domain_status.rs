@@ -1,13 +1,18 @@
 //! Domain status module for querying the verification status of a domain.
 
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres};
+
+use crate::auth::AuthenticatedUser;
+use crate::error::DomainError;
+
 /// Represents a query for domain status.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DomainStatusQuery {
-    /// The ID of the user querying the domain status.
-    pub user_id: String,
+    /// The user whose record is being queried. Only an admin may supply a
+    /// value other than their own id; plain users are scoped to themselves.
+    pub user_id: Option<String>,
     /// The domain being queried.
     pub domain: String,
 }
@@ -16,7 +21,12 @@ pub struct DomainStatusQuery {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DomainStatusResponse {
     /// The status of the domain ownership verification.
-    status: String
+    status: String,
+    /// Seconds until the challenge expires, or `None` when not applicable
+    /// (already verified or unauthorized). Negative values mean
+    /// the challenge has already expired.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in_seconds: Option<i64>,
 }
 
 /// Queries the status of domain ownership verification.
@@ -27,39 +37,47 @@ pub struct DomainStatusResponse {
 ///
 /// # Arguments
 ///
-/// * `query` - A web::Query containing the user ID and domain to check.
+/// * `auth` - The authenticated identity extracted from the bearer token.
+/// * `query` - A web::Query containing the optional user ID and domain to check.
 /// * `db_pool` - A connection pool for the database.
 ///
 /// # Returns
 ///
-/// An implementation of Responder, which will be a JSON response containing
-/// the status of the domain ownership verification.
+/// A `Result` carrying the JSON status response, or a `DomainError`.
 pub async fn query_domain_status(
+    auth: AuthenticatedUser,
     query: web::Query<DomainStatusQuery>,
     db_pool: web::Data<Pool<Postgres>>,
-) -> impl Responder {
-    let is_verified = match sqlx::query!(
-        "SELECT is_verified FROM txt_records WHERE user_id = $1 AND domain = $2",
-        query.user_id,
+) -> Result<HttpResponse, DomainError> {
+    // Resolve the target user: default to the caller, but let an admin inspect
+    // any user's records. A plain user asking about someone else is forbidden.
+    let target_user = query.user_id.clone().unwrap_or_else(|| auth.user_id.clone());
+    if !auth.can_access(&target_user) {
+        return Ok(HttpResponse::Forbidden().json(DomainStatusResponse {
+            status: "Not authorized to query this user's records".to_string(),
+            expires_in_seconds: None,
+        }));
+    }
+
+    let record = sqlx::query!(
+        "SELECT is_verified, expires_at FROM txt_records WHERE user_id = $1 AND domain = $2",
+        target_user,
         query.domain
     )
     .fetch_optional(db_pool.get_ref())
-    .await
-    {
-        Ok(Some(record)) => record.is_verified,
-        Ok(None) => return HttpResponse::NotFound().json(DomainStatusResponse {
-            status: "Record not found".to_string()
-        }),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
-    };
+    .await?
+    .ok_or(DomainError::RecordNotFound)?;
 
-    if is_verified {
-        HttpResponse::Ok().json(DomainStatusResponse {
-            status: "Domain ownership verified".to_string()
-        })
+    if record.is_verified {
+        Ok(HttpResponse::Ok().json(DomainStatusResponse {
+            status: "Domain ownership verified".to_string(),
+            expires_in_seconds: None,
+        }))
     } else {
-        HttpResponse::Ok().json(DomainStatusResponse {
-            status: "Record found but not yet verified".to_string()
-        })
+        let expires_in_seconds = (record.expires_at - chrono::Utc::now()).num_seconds();
+        Ok(HttpResponse::Ok().json(DomainStatusResponse {
+            status: "Record found but not yet verified".to_string(),
+            expires_in_seconds: Some(expires_in_seconds),
+        }))
     }
 }
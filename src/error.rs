@@ -0,0 +1,69 @@
+//! Unified error type for the DNS verification service.
+//!
+//! Handlers return `Result<HttpResponse, DomainError>` and lean on `?` rather
+//! than hand-rolling `HttpResponse::InternalServerError`. `DomainError`
+//! implements actix's [`ResponseError`] so each variant maps to the right
+//! status code and a consistent JSON body. Database errors never leak their
+//! raw driver message to clients; a `DnsLookup` error carries its message text.
+
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use serde::Serialize;
+use thiserror::Error;
+
+/// The errors a handler can surface.
+#[derive(Debug, Error)]
+pub enum DomainError {
+    /// A database operation failed.
+    #[error("database error")]
+    Db(#[source] sqlx::Error),
+    /// No matching record was found.
+    #[error("record not found")]
+    RecordNotFound,
+    /// The domain has already been verified.
+    #[error("domain ownership is already verified")]
+    AlreadyVerified,
+    /// A DNS lookup failed.
+    #[error("DNS lookup failed: {0}")]
+    DnsLookup(String),
+    /// A record already exists for this user and domain.
+    #[error("a record already exists for this domain")]
+    Duplicate,
+}
+
+/// The JSON body returned for every error.
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+impl ResponseError for DomainError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            DomainError::Db(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            DomainError::RecordNotFound => StatusCode::NOT_FOUND,
+            DomainError::AlreadyVerified => StatusCode::CONFLICT,
+            DomainError::DnsLookup(_) => StatusCode::BAD_GATEWAY,
+            DomainError::Duplicate => StatusCode::CONFLICT,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(ErrorBody {
+            error: &self.to_string(),
+        })
+    }
+}
+
+impl From<sqlx::Error> for DomainError {
+    /// Maps a unique-constraint violation to [`DomainError::Duplicate`] (409)
+    /// so that a racing INSERT on `(user_id, domain)` in `generate_txt_record`
+    /// surfaces as a conflict rather than an opaque 500.
+    fn from(err: sqlx::Error) -> Self {
+        if let Some(db_err) = err.as_database_error() {
+            if db_err.is_unique_violation() {
+                return DomainError::Duplicate;
+            }
+        }
+        DomainError::Db(err)
+    }
+}
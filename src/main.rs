@@ -1,7 +1,12 @@
+mod auth;
+mod error;
+mod poller;
+mod resolver;
 mod txt_generator;
 mod domain_status;
 mod record_verification;
 
+pub use auth::login;
 pub use txt_generator::generate_txt_record;
 pub use domain_status::query_domain_status;
 pub use record_verification::verify_txt_record;
@@ -11,6 +16,10 @@ use sqlx::postgres::PgPoolOptions;
 use dotenv::dotenv;
 use std::env;
 use actix_governor::{Governor, GovernorConfigBuilder};
+use std::sync::Arc;
+
+use crate::record_verification::SharedResolver;
+use crate::resolver::AsyncStdDnsResolver;
 
 
 
@@ -48,6 +57,13 @@ async fn main() -> std::io::Result<()> {
     
     println!("Starting server at http://127.0.0.1:8080");
 
+    // Build the shared DNS resolver injected into handlers and the poller.
+    let resolver: SharedResolver = Arc::new(AsyncStdDnsResolver::new().await);
+
+    // Spawn the background verification poller before serving requests so that
+    // pending domains are re-checked asynchronously as DNS propagates.
+    poller::spawn_verification_poller(pool.clone(), resolver.clone());
+
     // Configure rate limiter
     let governor_conf = GovernorConfigBuilder::default()
         .per_second(5)  // Allow 5 requests per second
@@ -62,6 +78,8 @@ async fn main() -> std::io::Result<()> {
             .wrap(middleware::Logger::default())
             .wrap(Governor::new(&governor_conf))
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::new(resolver.clone()))
+            .route("/login", web::post().to(login))
             .route("/generate_txt_record", web::post().to(generate_txt_record))
             .route("/verify_txt_record", web::post().to(verify_txt_record))
             .route("/domain_status", web::get().to(query_domain_status))
@@ -0,0 +1,148 @@
+//! Background verification poller.
+//!
+//! The service is normally request-driven: a record is only checked when a
+//! client hits `/verify_txt_record`. This module spawns a background task that
+//! periodically re-checks pending, unexpired challenges and flips
+//! `is_verified` once the record appears, so DNS propagation delays resolve
+//! themselves without the client polling.
+//!
+//! Failing domains are retried with exponential backoff driven by the
+//! `attempts` and `last_checked_at` columns, so a domain that never propagates
+//! is checked less and less aggressively.
+
+use std::time::Duration;
+
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use sqlx::{Pool, Postgres};
+
+use crate::record_verification::{record_is_present, SharedResolver};
+use crate::txt_generator::VerificationMethod;
+
+/// The default interval between poll cycles, in seconds.
+const DEFAULT_INTERVAL_SECONDS: u64 = 30;
+
+/// The default number of domains checked concurrently per cycle.
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The default backoff base, in seconds; doubled per failed attempt.
+const DEFAULT_BACKOFF_BASE_SECONDS: i64 = 60;
+
+/// The cap on the computed backoff interval, in seconds (one hour).
+const MAX_BACKOFF_SECONDS: i64 = 60 * 60;
+
+/// Reads the poll interval from `POLL_INTERVAL_SECONDS`.
+fn poll_interval_seconds() -> u64 {
+    std::env::var("POLL_INTERVAL_SECONDS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_INTERVAL_SECONDS)
+}
+
+/// Reads the per-cycle concurrency from `POLL_CONCURRENCY`.
+fn poll_concurrency() -> usize {
+    std::env::var("POLL_CONCURRENCY")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|n| *n > 0)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+}
+
+/// Reads the backoff base from `POLL_BACKOFF_BASE_SECONDS`.
+fn backoff_base_seconds() -> i64 {
+    std::env::var("POLL_BACKOFF_BASE_SECONDS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_BACKOFF_BASE_SECONDS)
+}
+
+/// Returns the backoff interval for a domain that has failed `attempts` times.
+///
+/// The interval grows exponentially (`base * 2^attempts`) and is capped at
+/// [`MAX_BACKOFF_SECONDS`].
+fn backoff_for(attempts: i32) -> i64 {
+    let shift = attempts.clamp(0, 16) as u32;
+    backoff_base_seconds()
+        .saturating_mul(1i64 << shift)
+        .min(MAX_BACKOFF_SECONDS)
+}
+
+/// Spawns the verification poller on the actix runtime.
+///
+/// Call this in `main` before `HttpServer::run`.
+pub fn spawn_verification_poller(pool: Pool<Postgres>, resolver: SharedResolver) {
+    actix_web::rt::spawn(async move {
+        run_poller(pool, resolver).await;
+    });
+}
+
+/// Runs the poll loop forever, sleeping `POLL_INTERVAL_SECONDS` between cycles.
+async fn run_poller(pool: Pool<Postgres>, resolver: SharedResolver) {
+    let interval = poll_interval_seconds();
+    let concurrency = poll_concurrency();
+    println!(
+        "Starting verification poller (interval {}s, concurrency {})",
+        interval, concurrency
+    );
+
+    loop {
+        if let Err(e) = poll_once(&pool, resolver.as_ref(), concurrency).await {
+            eprintln!("Verification poller error: {}", e);
+        }
+        actix_web::rt::time::sleep(Duration::from_secs(interval)).await;
+    }
+}
+
+/// Performs one poll cycle: scans pending challenges, checks the ones whose
+/// backoff has elapsed, and records the outcome.
+async fn poll_once(
+    pool: &Pool<Postgres>,
+    resolver: &dyn crate::resolver::DnsResolver,
+    concurrency: usize,
+) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT user_id, domain, record, record_type, attempts, last_checked_at \
+         FROM txt_records WHERE is_verified = false AND expires_at > now()"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now();
+    let due: Vec<_> = rows
+        .into_iter()
+        .filter(|row| match row.last_checked_at {
+            None => true,
+            Some(last) => (now - last).num_seconds() >= backoff_for(row.attempts),
+        })
+        .collect();
+
+    stream::iter(due)
+        .for_each_concurrent(concurrency, |row| async move {
+            let method = VerificationMethod::from_db(&row.record_type);
+            match record_is_present(resolver, method, &row.domain, &row.record).await {
+                Ok(true) => {
+                    let _ = sqlx::query!(
+                        "UPDATE txt_records SET is_verified = true, last_checked_at = now() \
+                         WHERE user_id = $1 AND domain = $2",
+                        row.user_id,
+                        row.domain
+                    )
+                    .execute(pool)
+                    .await;
+                }
+                Ok(false) | Err(_) => {
+                    let _ = sqlx::query!(
+                        "UPDATE txt_records SET attempts = attempts + 1, last_checked_at = now() \
+                         WHERE user_id = $1 AND domain = $2",
+                        row.user_id,
+                        row.domain
+                    )
+                    .execute(pool)
+                    .await;
+                }
+            }
+        })
+        .await;
+
+    Ok(())
+}
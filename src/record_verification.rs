@@ -2,105 +2,196 @@
 //!
 //! This module provides functionality to verify DNS TXT records for domain ownership verification.
 
-use actix_web::{web, HttpResponse, Responder};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use sqlx::{Pool, Postgres};
-use std::net::*;
-use async_std::prelude::*;
-use async_std_resolver::{resolver, config};
+
+use chrono::Utc;
+
+use crate::auth::AuthenticatedUser;
+use crate::error::DomainError;
+use crate::resolver::DnsResolver;
+use crate::txt_generator::{generate_instruction, verification_ttl_seconds, VerificationMethod};
+
+/// The maximum number of CNAME hops followed before giving up.
+const MAX_CNAME_DEPTH: usize = 8;
+
+/// A resolver shared across handlers and the background poller.
+pub type SharedResolver = Arc<dyn DnsResolver>;
 
 /// Represents a request to verify a TXT record.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VerificationRequest {
-    /// The ID of the user requesting verification.
-    user_id: String,
     /// The domain to verify.
     domain: String,
+    /// When `true`, an expired challenge is rotated (a fresh value is minted on
+    /// the same row) and the new instruction is returned instead of verifying.
+    #[serde(default)]
+    rotate: bool,
 }
 
-/// Looks up TXT records for a given domain.
+/// Collects the TXT records for a name, following CNAME redirects.
 ///
-/// # Arguments
-///
-/// * `domain` - A string slice that holds the domain name to look up.
-///
-/// # Returns
-///
-/// A `Result` containing a vector of strings (TXT records) if successful,
-/// or an error message as a string if the lookup fails.
-async fn lookup_txt_record(domain: &str) -> Result<Vec<String>, String> {
-    let resolver = resolver(
-        config::ResolverConfig::default(),
-        config::ResolverOpts::default(),
-    ).await;
-    
-    // Perform TXT lookup
-    let response = resolver.txt_lookup(domain).await
-        .map_err(|e| format!("TXT record lookup failed: {}", e))?;
-    
-    // Extract and collect all the TXT records
-    let records: Vec<String> = response.iter()
-        .flat_map(|rdata| rdata.txt_data().iter().map(|b| String::from_utf8_lossy(b).to_string()))
-        .collect();
-    
-    Ok(records)
+/// When the queried name is a CNAME to where the TXT actually lives, the chain
+/// is chased up to [`MAX_CNAME_DEPTH`] hops. A name already seen aborts the walk
+/// so a self-referential CNAME cannot loop forever.
+async fn txt_following_cname(
+    resolver: &dyn DnsResolver,
+    name: &str,
+) -> Result<Vec<String>, String> {
+    let mut current = name.to_string();
+    let mut seen = HashSet::new();
+
+    for _ in 0..=MAX_CNAME_DEPTH {
+        if !seen.insert(current.clone()) {
+            return Err(format!("CNAME cycle detected at {}", current));
+        }
+
+        let txt = resolver.txt(&current).await?;
+        if !txt.is_empty() {
+            return Ok(txt);
+        }
+
+        // No TXT here — follow the CNAME redirect if there is one.
+        match resolver.cname(&current).await?.into_iter().next() {
+            Some(target) => current = target,
+            None => return Ok(Vec::new()),
+        }
+    }
+
+    Err(format!("CNAME chain for {} exceeded {} hops", name, MAX_CNAME_DEPTH))
+}
+
+/// Resolves the domain with the method's lookup and reports whether the
+/// expected record is present.
+pub(crate) async fn record_is_present(
+    resolver: &dyn DnsResolver,
+    method: VerificationMethod,
+    domain: &str,
+    expected_record: &str,
+) -> Result<bool, String> {
+    let found = match method {
+        VerificationMethod::Txt => txt_following_cname(resolver, domain).await?,
+        VerificationMethod::Cname => resolver.cname(domain).await?,
+        VerificationMethod::A => resolver.a(domain).await?,
+    };
+    Ok(found.iter().any(|value| value == expected_record))
 }
 
-/// Verifies a TXT record for domain ownership.
+/// Verifies a DNS record for domain ownership.
 ///
 /// This function checks if the expected TXT record exists for the given domain.
 /// If found, it marks the record as verified in the database.
 ///
 /// # Arguments
 ///
-/// * `verification_request` - A JSON payload containing the user ID and domain to verify.
+/// * `auth` - The authenticated identity extracted from the bearer token.
+/// * `verification_request` - A JSON payload containing the domain to verify.
 /// * `db_pool` - A connection pool for the database.
+/// * `resolver` - The shared DNS resolver used to perform the lookups.
 ///
 /// # Returns
 ///
-/// An implementation of `Responder`, which will be a JSON response indicating
-/// the result of the verification process.
+/// A `Result` carrying the JSON verification response, or a `DomainError`.
 pub async fn verify_txt_record(
+    auth: AuthenticatedUser,
     verification_request: web::Json<VerificationRequest>,
     db_pool: web::Data<Pool<Postgres>>,
-) -> impl Responder {
+    resolver: web::Data<SharedResolver>,
+) -> Result<HttpResponse, DomainError> {
     let domain = &verification_request.domain;
-    let user_id = &verification_request.user_id;
+    let user_id = &auth.user_id;
 
-    // Fetch the expected TXT record from the database
-    let expected_record = match sqlx::query!(
-        "SELECT record FROM txt_records WHERE user_id = $1 AND domain = $2 AND is_verified = false",
+    // Fetch the expected record, its type and expiry from the database
+    let record = sqlx::query!(
+        "SELECT record, record_type, expires_at FROM txt_records WHERE user_id = $1 AND domain = $2 AND is_verified = false",
         user_id,
         domain
     )
     .fetch_optional(db_pool.get_ref())
-    .await
-    {
-        Ok(Some(record)) => record.record,
-        Ok(None) => return HttpResponse::NotFound().json("Record not found or already verified"),
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
-    };
+    .await?
+    .ok_or(DomainError::RecordNotFound)?;
+
+    let expected_record = record.record;
+    let method = VerificationMethod::from_db(&record.record_type);
+    let expires_at = record.expires_at;
 
-    // Lookup the TXT records for the domain
-    match lookup_txt_record(domain).await {
-        Ok(txt_records) => {
-            if txt_records.contains(&expected_record) {
-                // Update the database to mark the record as verified
-                match sqlx::query!(
-                    "UPDATE txt_records SET is_verified = true WHERE user_id = $1 AND domain = $2",
-                    user_id,
-                    domain
-                )
-                .execute(db_pool.get_ref())
-                .await
-                {
-                    Ok(_) => HttpResponse::Ok().json("TXT record verified successfully"),
-                    Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
-                }
-            } else {
-                HttpResponse::BadRequest().json("TXT record not found")
-            }
+    // Reject expired challenges. When the caller asks to rotate, mint a fresh
+    // challenge on the same row and return the new instruction instead.
+    if expires_at <= Utc::now() {
+        if !verification_request.rotate {
+            return Ok(HttpResponse::BadRequest()
+                .json("Verification challenge has expired; re-request with rotate = true"));
         }
-        Err(e) => HttpResponse::InternalServerError().body(e),
+
+        let dns_record = generate_instruction(domain, method).map_err(DomainError::DnsLookup)?;
+        let new_expires_at = Utc::now() + chrono::Duration::seconds(verification_ttl_seconds());
+
+        sqlx::query!(
+            "UPDATE txt_records SET record = $1, expires_at = $2 WHERE user_id = $3 AND domain = $4",
+            dns_record.record,
+            new_expires_at,
+            user_id,
+            domain
+        )
+        .execute(db_pool.get_ref())
+        .await?;
+
+        return Ok(HttpResponse::Ok().json(dns_record));
+    }
+
+    // Resolve the domain with the lookup matching the record type
+    let present = record_is_present(resolver.get_ref().as_ref(), method, domain, &expected_record)
+        .await
+        .map_err(DomainError::DnsLookup)?;
+
+    if !present {
+        return Ok(HttpResponse::BadRequest().json("Verification record not found"));
+    }
+
+    // Update the database to mark the record as verified
+    sqlx::query!(
+        "UPDATE txt_records SET is_verified = true WHERE user_id = $1 AND domain = $2",
+        user_id,
+        domain
+    )
+    .execute(db_pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json("Record verified successfully"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resolver::MockDnsResolver;
+
+    #[actix_web::test]
+    async fn follows_cname_to_the_txt_record() {
+        let mut mock = MockDnsResolver::default();
+        // The queried name is a CNAME to where the TXT actually lives.
+        mock.cname
+            .insert("example.com".to_string(), vec!["verify.example.net".to_string()]);
+        mock.txt
+            .insert("verify.example.net".to_string(), vec!["token=abc".to_string()]);
+
+        let present = record_is_present(&mock, VerificationMethod::Txt, "example.com", "token=abc")
+            .await
+            .unwrap();
+        assert!(present);
+    }
+
+    #[actix_web::test]
+    async fn aborts_on_cname_cycle() {
+        let mut mock = MockDnsResolver::default();
+        mock.cname
+            .insert("a.example.com".to_string(), vec!["a.example.com".to_string()]);
+
+        let result =
+            record_is_present(&mock, VerificationMethod::Txt, "a.example.com", "token=abc").await;
+        assert!(result.is_err());
     }
 }
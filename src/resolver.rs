@@ -0,0 +1,123 @@
+//! DNS resolution behind a trait so handlers are testable without real network
+//! access.
+//!
+//! [`DnsResolver`] exposes the three lookups the verifier needs (`txt`,
+//! `cname`, `a`). The real implementation wraps `async-std-resolver`; the mock
+//! returns scripted responses for a given set of queries, letting the
+//! verification path be exercised deterministically.
+
+#[cfg(test)]
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use async_std_resolver::{config, proto::rr::RecordType, resolver, AsyncStdResolver};
+
+/// Abstracts the DNS lookups used during verification.
+#[async_trait]
+pub trait DnsResolver: Send + Sync {
+    /// Returns the TXT records published at `name`.
+    async fn txt(&self, name: &str) -> Result<Vec<String>, String>;
+    /// Returns the CNAME targets published at `name`.
+    async fn cname(&self, name: &str) -> Result<Vec<String>, String>;
+    /// Returns the IPv4 (A) records published at `name`.
+    async fn a(&self, name: &str) -> Result<Vec<String>, String>;
+}
+
+/// The production resolver backed by `async-std-resolver`.
+pub struct AsyncStdDnsResolver {
+    inner: AsyncStdResolver,
+}
+
+impl AsyncStdDnsResolver {
+    /// Builds a resolver from the system's default configuration.
+    pub async fn new() -> Self {
+        let inner = resolver(
+            config::ResolverConfig::default(),
+            config::ResolverOpts::default(),
+        )
+        .await;
+        Self { inner }
+    }
+}
+
+#[async_trait]
+impl DnsResolver for AsyncStdDnsResolver {
+    async fn txt(&self, name: &str) -> Result<Vec<String>, String> {
+        let response = self
+            .inner
+            .txt_lookup(name)
+            .await
+            .map_err(|e| format!("TXT record lookup failed: {}", e))?;
+
+        Ok(response
+            .iter()
+            .flat_map(|rdata| {
+                rdata
+                    .txt_data()
+                    .iter()
+                    .map(|b| String::from_utf8_lossy(b).to_string())
+            })
+            .collect())
+    }
+
+    async fn cname(&self, name: &str) -> Result<Vec<String>, String> {
+        let response = self
+            .inner
+            .lookup(name, RecordType::CNAME)
+            .await
+            .map_err(|e| format!("CNAME record lookup failed: {}", e))?;
+
+        Ok(response
+            .iter()
+            .filter_map(|rdata| {
+                rdata
+                    .as_cname()
+                    .map(|name| name.to_utf8().trim_end_matches('.').to_string())
+            })
+            .collect())
+    }
+
+    async fn a(&self, name: &str) -> Result<Vec<String>, String> {
+        let response = self
+            .inner
+            .ipv4_lookup(name)
+            .await
+            .map_err(|e| format!("A record lookup failed: {}", e))?;
+
+        Ok(response
+            .iter()
+            .map(|ip| std::net::Ipv4Addr::from(*ip).to_string())
+            .collect())
+    }
+}
+
+/// A resolver returning scripted responses, for deterministic tests.
+///
+/// Each map is keyed by query name; an absent key resolves to an empty vector
+/// (`Ok`), whereas [`AsyncStdDnsResolver`] reports a failed lookup as an `Err`.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MockDnsResolver {
+    /// Scripted TXT responses keyed by query name.
+    pub txt: HashMap<String, Vec<String>>,
+    /// Scripted CNAME targets keyed by query name.
+    pub cname: HashMap<String, Vec<String>>,
+    /// Scripted A responses keyed by query name.
+    pub a: HashMap<String, Vec<String>>,
+}
+
+#[cfg(test)]
+#[async_trait]
+impl DnsResolver for MockDnsResolver {
+    async fn txt(&self, name: &str) -> Result<Vec<String>, String> {
+        Ok(self.txt.get(name).cloned().unwrap_or_default())
+    }
+
+    async fn cname(&self, name: &str) -> Result<Vec<String>, String> {
+        Ok(self.cname.get(name).cloned().unwrap_or_default())
+    }
+
+    async fn a(&self, name: &str) -> Result<Vec<String>, String> {
+        Ok(self.a.get(name).cloned().unwrap_or_default())
+    }
+}
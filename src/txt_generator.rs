@@ -3,20 +3,81 @@
 //! This module provides functionality to generate and manage TXT records
 //! for domain ownership verification purposes.
 
-use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, Pool, Postgres};
+use sqlx::{Pool, Postgres};
 use ksuid::Ksuid;
+use chrono::Utc;
 
-/// Represents the configuration for generating a TXT record.
+use crate::auth::AuthenticatedUser;
+use crate::error::DomainError;
+
+/// The default lifetime of a verification challenge, in seconds.
+const DEFAULT_TTL_SECONDS: i64 = 60 * 60 * 24;
+
+/// The environment variable overriding the challenge lifetime.
+const TTL_ENV: &str = "VERIFICATION_TTL_SECONDS";
+
+/// The suffix appended to the KSUID to form CNAME verification targets
+/// (`<ksuid>.verify.yourservice.com`).
+const CNAME_TARGET_SUFFIX: &str = "verify.yourservice.com";
+
+/// The environment variable holding the expected IP for A-record verification.
+const A_RECORD_ENV: &str = "VERIFY_A_RECORD_IP";
+
+/// The method a user proves domain ownership with.
+///
+/// TXT is the default, but users who cannot add TXT records (for example apex
+/// domains behind certain providers) can prove ownership with a CNAME target or
+/// an expected A record instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VerificationMethod {
+    /// A TXT record carrying the challenge value.
+    Txt,
+    /// A CNAME pointing at a service-owned target.
+    Cname,
+    /// An A record pointing at a service-owned IP.
+    A,
+}
+
+impl Default for VerificationMethod {
+    fn default() -> Self {
+        VerificationMethod::Txt
+    }
+}
+
+impl VerificationMethod {
+    /// Returns the lowercase tag persisted in the `record_type` column.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerificationMethod::Txt => "txt",
+            VerificationMethod::Cname => "cname",
+            VerificationMethod::A => "a",
+        }
+    }
+
+    /// Parses a method back from its persisted tag, defaulting to TXT.
+    pub fn from_db(tag: &str) -> Self {
+        match tag {
+            "cname" => VerificationMethod::Cname,
+            "a" => VerificationMethod::A,
+            _ => VerificationMethod::Txt,
+        }
+    }
+}
+
+/// Represents the configuration for generating a DNS verification record.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct TxtRecordGenerator {
-    /// The domain for which the TXT record is being generated.
+pub struct DnsRecordGenerator {
+    /// The domain for which the record is being generated.
     domain: String,
-    /// The attribute name for the TXT record.
+    /// The attribute name for the record.
     record_attribute: String,
-    /// The value for the TXT record attribute.
+    /// The value for the record attribute (a fresh KSUID challenge).
     record_attribute_value: String,
+    /// The method used to prove ownership.
+    method: VerificationMethod,
 }
 
 /// Represents the instruction for creating a DNS record.
@@ -31,12 +92,13 @@ pub struct DnsRecordInstruction {
 }
 
 /// Represents a user's request for generating a TXT record.
-#[derive(Debug, Serialize, Deserialize)]      
+#[derive(Debug, Serialize, Deserialize)]
 pub struct UserRequest {
-    /// The ID of the user making the request.
-    user_id: String,
-    /// The domain for which the TXT record is requested.
+    /// The domain for which the record is requested.
     domain: String,
+    /// The verification method to use. Defaults to TXT when omitted.
+    #[serde(default)]
+    method: VerificationMethod,
 }
 
 /// Represents the response to a TXT record generation request.
@@ -48,8 +110,8 @@ pub struct TxtRecordResponse {
     dns_record: DnsRecordInstruction,
 }
 
-impl TxtRecordGenerator {
-    /// Validates the TXT record configuration.
+impl DnsRecordGenerator {
+    /// Validates the record configuration.
     ///
     /// # Returns
     ///
@@ -71,6 +133,17 @@ impl TxtRecordGenerator {
     }
 }
 
+/// Returns the configured challenge lifetime in seconds.
+///
+/// Reads `VERIFICATION_TTL_SECONDS`, falling back to [`DEFAULT_TTL_SECONDS`]
+/// when the variable is unset or cannot be parsed.
+pub fn verification_ttl_seconds() -> i64 {
+    std::env::var(TTL_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_TTL_SECONDS)
+}
+
 /// Generates a new KSUID (K-Sortable Unique IDentifier).
 ///
 /// # Returns
@@ -91,24 +164,82 @@ fn generate_ksuid() -> String {
 /// Returns a `Result` containing either a `DnsRecordInstruction` if successful,
 /// or a `String` describing the error if unsuccessful.
 fn generate_txt_record_from_config(
-    config: &mut TxtRecordGenerator,
+    config: &mut DnsRecordGenerator,
 ) -> Result<DnsRecordInstruction, String> {
     config.validate()?;
 
-    let txt_record = format!("{}={}", config.record_attribute, config.record_attribute_value);
-
-    let instruction = DnsRecordInstruction {
-        domain: config.domain.clone(),
-        record: txt_record.clone(),
-        action: format!(
-            "Create a TXT record for the domain {} with the content {}",
-            config.domain, txt_record
-        ),
+    let instruction = match config.method {
+        VerificationMethod::Txt => {
+            let txt_record =
+                format!("{}={}", config.record_attribute, config.record_attribute_value);
+            DnsRecordInstruction {
+                domain: config.domain.clone(),
+                record: txt_record.clone(),
+                action: format!(
+                    "Create a TXT record for the domain {} with the content {}",
+                    config.domain, txt_record
+                ),
+            }
+        }
+        VerificationMethod::Cname => {
+            let target = format!("{}.{}", config.record_attribute_value, CNAME_TARGET_SUFFIX);
+            DnsRecordInstruction {
+                domain: config.domain.clone(),
+                record: target.clone(),
+                action: format!(
+                    "Create a CNAME record for the domain {} pointing at {}",
+                    config.domain, target
+                ),
+            }
+        }
+        VerificationMethod::A => {
+            let ip = std::env::var(A_RECORD_ENV)
+                .map_err(|_| format!("{} must be set for A-record verification", A_RECORD_ENV))?;
+            DnsRecordInstruction {
+                domain: config.domain.clone(),
+                record: ip.clone(),
+                action: format!(
+                    "Create an A record for the domain {} pointing at {}",
+                    config.domain, ip
+                ),
+            }
+        }
     };
 
     Ok(instruction)
 }
 
+/// Builds a fresh verification instruction for a domain and method.
+///
+/// This mints a new KSUID challenge and renders the matching DNS instruction.
+/// It is shared by the generation handler and the rotation path so that a
+/// rotated challenge is produced exactly like a freshly generated one.
+///
+/// # Arguments
+///
+/// * `domain` - The domain the instruction is for.
+/// * `method` - The verification method to render.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `DnsRecordInstruction`, or a `String`
+/// describing the error.
+pub fn generate_instruction(
+    domain: &str,
+    method: VerificationMethod,
+) -> Result<DnsRecordInstruction, String> {
+    let txt_record_attribute_suffix = "_verification";
+
+    let mut dns_config = DnsRecordGenerator {
+        domain: domain.to_string(),
+        record_attribute: format!("{}_{}", domain.replace(".", "_"), txt_record_attribute_suffix),
+        record_attribute_value: generate_ksuid(),
+        method,
+    };
+
+    generate_txt_record_from_config(&mut dns_config)
+}
+
 /// Handles the generation of a TXT record for domain ownership verification.
 ///
 /// This function checks if a verification record already exists for the given user and domain.
@@ -118,95 +249,81 @@ fn generate_txt_record_from_config(
 ///
 /// # Arguments
 ///
+/// * `auth` - The authenticated identity extracted from the bearer token.
 /// * `user_request` - A JSON payload containing the user's request.
 /// * `db_pool` - A connection pool for the database.
 ///
 /// # Returns
 ///
-/// Returns an implementation of `Responder`, which will be a JSON response
-/// containing either the generated TXT record or an error message.
+/// A `Result` carrying the JSON record response, or a `DomainError`.
 pub async fn generate_txt_record(
+    auth: AuthenticatedUser,
     user_request: web::Json<UserRequest>,
     db_pool: web::Data<Pool<Postgres>>,
-) -> impl Responder {
+) -> Result<HttpResponse, DomainError> {
     let domain = &user_request.domain;
-    let user_id = &user_request.user_id;
+    let user_id = &auth.user_id;
 
     if domain.trim().is_empty() {
-        return HttpResponse::BadRequest().json("Domain is empty");
+        return Ok(HttpResponse::BadRequest().json("Domain is empty"));
     }
 
     // Check if a verification code already exists for this user_id and domain
-    match sqlx::query!(
-        "SELECT record, is_verified FROM txt_records WHERE user_id = $1 AND domain = $2",
+    let existing = sqlx::query!(
+        "SELECT record, record_type, is_verified FROM txt_records WHERE user_id = $1 AND domain = $2",
         user_id,
         domain
     )
     .fetch_optional(db_pool.get_ref())
-    .await
-    {
-        Ok(Some(existing_record)) => {
-            if existing_record.is_verified {
-                // If the user is already verified, return a message
-                return HttpResponse::Ok().json("This domain onwnership is already verified");
-            } else {
-                // If a record exists but not verified, return the existing verification code
-                let dns_record = DnsRecordInstruction {
-                    domain: domain.to_string(),
-                    record: existing_record.record.clone(),
-                    action: format!(
-                        "Use existing TXT record for the domain {} with the content {}",
-                        domain, existing_record.record.clone()
-                    ),
-                };
-                let response = TxtRecordResponse {
-                    user_id: user_id.to_string(),
-                    dns_record,
-                };
-                return HttpResponse::Ok().json(response);
-            }
-        }
-        Ok(None) => {
-            // If no record exists, proceed to generate a new one
-            println!("No record exists, proceeding to generate a new one");
-            let txt_record_attribute_suffix = "_verification";
-
-            let mut txt_config = TxtRecordGenerator {
-                domain: domain.to_string(),
-                record_attribute: format!("{}_{}", domain.replace(".", "_"), txt_record_attribute_suffix),
-                record_attribute_value: generate_ksuid(),
-            };
-
-            match generate_txt_record_from_config(&mut txt_config) {
-                Ok(dns_record) => {
-                    // Store the new record in the database
-                    match sqlx::query!(
-                        "INSERT INTO txt_records (user_id, domain, record, is_verified) VALUES ($1, $2, $3, $4)",
-                        user_id,
-                        dns_record.domain,
-                        dns_record.record,
-                        false
-                    )
-                    .execute(db_pool.get_ref())
-                    .await
-                    {
-                        Ok(_) => {
-                            let response = TxtRecordResponse {
-                                user_id: user_id.to_string(),
-                                dns_record,
-                            };
-                            HttpResponse::Ok().json(response)
-                        }
-                        Err(e) => HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
-                    }
-                }
-                Err(e) => HttpResponse::InternalServerError().body(e),
-            }
-            
+    .await?;
 
+    if let Some(existing_record) = existing {
+        if existing_record.is_verified {
+            // If the user is already verified, signal the conflict
+            return Err(DomainError::AlreadyVerified);
         }
-        Err(e) => return HttpResponse::InternalServerError().body(format!("Database error: {}", e)),
+
+        // If a record exists but not verified, return the existing verification code
+        let method = VerificationMethod::from_db(&existing_record.record_type);
+        let dns_record = DnsRecordInstruction {
+            domain: domain.to_string(),
+            record: existing_record.record.clone(),
+            action: format!(
+                "Use existing {} record for the domain {} with the content {}",
+                method.as_str().to_uppercase(), domain, existing_record.record.clone()
+            ),
+        };
+        let response = TxtRecordResponse {
+            user_id: user_id.to_string(),
+            dns_record,
+        };
+        return Ok(HttpResponse::Ok().json(response));
     }
 
-    
+    // If no record exists, proceed to generate a new one
+    println!("No record exists, proceeding to generate a new one");
+    let method = user_request.method;
+    let expires_at = Utc::now() + chrono::Duration::seconds(verification_ttl_seconds());
+
+    let dns_record = generate_instruction(domain, method).map_err(DomainError::DnsLookup)?;
+
+    // Store the new record in the database. A racing INSERT that violates the
+    // unique constraint on (user_id, domain) surfaces as DomainError::Duplicate.
+    sqlx::query!(
+        "INSERT INTO txt_records (user_id, domain, record, record_type, is_verified, expires_at) VALUES ($1, $2, $3, $4, $5, $6)",
+        user_id,
+        dns_record.domain,
+        dns_record.record,
+        method.as_str(),
+        false,
+        expires_at
+    )
+    .execute(db_pool.get_ref())
+    .await?;
+
+    let response = TxtRecordResponse {
+        user_id: user_id.to_string(),
+        dns_record,
+    };
+    Ok(HttpResponse::Ok().json(response))
 }